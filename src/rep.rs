@@ -3,25 +3,71 @@ use crate::endpoint::Endpoint;
 use crate::error::*;
 use crate::fair_queue::{FairQueue, QueueInner};
 use crate::transport::AcceptStopHandle;
+use crate::util::PeerIdentity;
 use crate::*;
-use crate::{SocketType, ZmqResult};
+use crate::{SocketOptions, SocketType, ZmqResult};
 use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
 use dashmap::DashMap;
+use futures::channel::mpsc;
+use futures::future::{poll_fn, select, Either};
+use futures::FutureExt;
 use futures::SinkExt;
 use futures::StreamExt;
+use futures_timer::Delay;
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const PING_COMMAND_NAME: &str = "PING";
+const PONG_COMMAND_NAME: &str = "PONG";
+
+/// Marks the first body frame of a chunk belonging to a [`RepSocket::send_stream`] transfer, so
+/// the receiving side can tell it apart from an ordinary single-part reply. Only a peer that
+/// itself reassembles on this exact 3-frame marker/header/payload contract (this crate's own
+/// socket types do) will see a streamed reply as anything but three raw frames; a plain REQ/DEALER
+/// peer that has no notion of this framing is not a supported consumer of `send_stream`.
+const STREAM_CHUNK_MARKER: &[u8] = b"zmq.rs/stream-chunk";
+/// Size of each chunk handed to the wire by [`RepSocket::send_stream`].
+const STREAM_CHUNK_SIZE: usize = 32 * 1024;
+
+/// One large message queued by [`RepSocket::send_stream`], broken into chunks still waiting to
+/// be written out. The per-peer scheduler round-robins between every active `StreamJob` for a
+/// peer so a single huge reply can't starve the others.
+struct StreamJob {
+    stream_id: u64,
+    envelope: Option<ZmqMessage>,
+    chunks: VecDeque<Bytes>,
+}
+
+/// Result of folding one incoming chunk into [`RepSocket::accumulate_chunk`]'s per-peer
+/// reassembly state.
+enum ChunkOutcome {
+    /// More chunks are still expected before this stream completes.
+    Continue,
+    /// The final chunk arrived; this is the reassembled message.
+    Completed(ZmqMessage),
+    /// The reassembled buffer grew past `ZMQ_MAXMSGSIZE`; the stream was aborted and discarded.
+    Oversized,
+}
 
 struct RepPeer {
     pub(crate) _identity: PeerIdentity,
-    pub(crate) send_queue: ZmqFramedWrite,
+    pub(crate) send_queue: mpsc::Sender<Message>,
+    pub(crate) stream_queue: mpsc::UnboundedSender<StreamJob>,
 }
 
 struct RepSocketBackend {
     pub(crate) peers: DashMap<PeerIdentity, RepPeer>,
     fair_queue_inner: Arc<Mutex<QueueInner<ZmqFramedRead, PeerIdentity>>>,
     socket_monitor: Mutex<Option<mpsc::Sender<SocketEvent>>>,
+    socket_options: SocketOptions,
+    last_activity: DashMap<PeerIdentity, Instant>,
+    last_sent: DashMap<PeerIdentity, Instant>,
+    heartbeat_seq: AtomicU64,
+    stream_seq: AtomicU64,
 }
 
 pub struct RepSocket {
@@ -30,29 +76,499 @@ pub struct RepSocket {
     current_request: Option<PeerIdentity>,
     fair_queue: FairQueue<ZmqFramedRead, PeerIdentity>,
     binds: HashMap<Endpoint, AcceptStopHandle>,
+    stream_reassembly: HashMap<(PeerIdentity, u64), BytesMut>,
 }
 
+/// An opaque routing envelope captured by [`RepSocket::recv_routed`] and replayed by
+/// [`RepSocket::send_routed`] to address a specific peer directly, bypassing the implicit
+/// single in-flight request/reply pairing used by [`SocketRecv`]/[`SocketSend`].
+pub struct RoutingEnvelope(ZmqMessage);
+
 impl Drop for RepSocket {
     fn drop(&mut self) {
         self.backend.shutdown();
     }
 }
 
-#[async_trait]
-impl Socket for RepSocket {
-    fn new() -> Self {
+impl RepSocket {
+    /// Create a [`RepSocket`] that honors the given [`SocketOptions`] instead of the defaults
+    /// used by [`Socket::new`].
+    pub fn with_options(options: SocketOptions) -> Self {
         let fair_queue = FairQueue::new(true);
         Self {
             backend: Arc::new(RepSocketBackend {
                 peers: DashMap::new(),
                 fair_queue_inner: fair_queue.inner(),
                 socket_monitor: Mutex::new(None),
+                socket_options: options,
+                last_activity: DashMap::new(),
+                last_sent: DashMap::new(),
+                heartbeat_seq: AtomicU64::new(0),
+                stream_seq: AtomicU64::new(0),
             }),
             envelope: None,
             current_request: None,
             fair_queue,
             binds: HashMap::new(),
+            stream_reassembly: HashMap::new(),
+        }
+    }
+
+    /// Stream a large reply to the peer that made the current request as a sequence of
+    /// fixed-size chunks instead of flushing it as a single multi-megabyte frame. Chunks are
+    /// enqueued round-robin against any other in-flight streamed replies to the same peer so
+    /// this doesn't cause head-of-line blocking for other requests.
+    ///
+    /// The peer must understand the chunk marker/header/payload framing documented on
+    /// [`STREAM_CHUNK_MARKER`] to reassemble the reply; this is not a transparent drop-in
+    /// replacement for [`SocketSend::send`] against an arbitrary REQ/DEALER implementation.
+    pub async fn send_stream(&mut self, message: ZmqMessage) -> ZmqResult<()> {
+        match self.current_request.take() {
+            Some(peer_id) => {
+                let envelope = self.envelope.take();
+                self.backend.enqueue_stream(&peer_id, envelope, message)
+            }
+            None => Err(ZmqError::ReturnToSender {
+                reason: "Unable to send reply. No request in progress",
+                message,
+            }),
+        }
+    }
+
+    /// Receive a request bypassing the implicit single in-flight request/reply pairing used by
+    /// [`SocketRecv::recv`], returning the identity of the peer that sent it and its routing
+    /// envelope alongside the payload. This is the primitive a ROUTER-style load-balancing
+    /// broker needs: it can remember a worker's identity from one `recv_routed` call and later
+    /// target that exact peer with [`RepSocket::send_routed`].
+    pub async fn recv_routed(&mut self) -> ZmqResult<(PeerIdentity, RoutingEnvelope, ZmqMessage)> {
+        loop {
+            let next = match self.backend.socket_options.recv_timeout() {
+                Some(timeout) => match select(self.fair_queue.next(), Delay::new(timeout)).await {
+                    Either::Left((next, _)) => next,
+                    Either::Right(_) => return Err(ZmqError::Timeout),
+                },
+                None => self.fair_queue.next().await,
+            };
+            match next {
+                Some((peer_id, Ok(message))) => {
+                    self.backend.touch(&peer_id);
+                    match message {
+                        Message::Message(m) => {
+                            let (envelope, data) = match split_envelope(m) {
+                                Some(parts) => parts,
+                                None => {
+                                    self.backend.drop_peer(&peer_id);
+                                    self.stream_reassembly.retain(|(p, _), _| p != &peer_id);
+                                    continue;
+                                }
+                            };
+                            if let Some((stream_id, more, payload)) = decode_chunk(&data) {
+                                match self.accumulate_chunk(&peer_id, stream_id, more, payload) {
+                                    ChunkOutcome::Continue => continue,
+                                    ChunkOutcome::Completed(message) => {
+                                        return Ok((peer_id, RoutingEnvelope(envelope), message))
+                                    }
+                                    ChunkOutcome::Oversized => {
+                                        let reply =
+                                            ZmqMessage::from("Message exceeds ZMQ_MAXMSGSIZE");
+                                        self.backend
+                                            .reject_oversized(&peer_id, Some(envelope), reply)
+                                            .await;
+                                        continue;
+                                    }
+                                }
+                            }
+                            if let Some(max_size) = self.backend.socket_options.max_message_size() {
+                                let size: usize = data.iter().map(|frame| frame.len()).sum();
+                                if size > max_size {
+                                    let reply = ZmqMessage::from("Message exceeds ZMQ_MAXMSGSIZE");
+                                    self.backend
+                                        .reject_oversized(&peer_id, Some(envelope), reply)
+                                        .await;
+                                    continue;
+                                }
+                            }
+                            return Ok((peer_id, RoutingEnvelope(envelope), data));
+                        }
+                        Message::Command(command) => {
+                            self.backend.handle_command(&peer_id, command);
+                            continue;
+                        }
+                    }
+                }
+                Some((peer_id, Err(_))) => {
+                    self.backend.drop_peer(&peer_id);
+                    self.stream_reassembly.retain(|(p, _), _| p != &peer_id);
+                    continue;
+                }
+                None => return Err(ZmqError::NoMessage),
+            }
+        }
+    }
+
+    /// Send to an explicit peer with an explicit routing envelope, instead of the single
+    /// implicit in-progress request tracked by [`SocketSend::send`]. This is what a ROUTER-style
+    /// load-balancing broker needs to forward a client's request to a specific chosen worker.
+    pub async fn send_routed(
+        &mut self,
+        peer_id: PeerIdentity,
+        envelope: RoutingEnvelope,
+        mut message: ZmqMessage,
+    ) -> ZmqResult<()> {
+        if let Some(mut peer) = self.backend.peers.get_mut(&peer_id) {
+            message.prepend(&envelope.0);
+            match self.backend.socket_options.send_timeout() {
+                Some(timeout) => match select(
+                    poll_fn(|cx| peer.send_queue.poll_ready(cx)),
+                    Delay::new(timeout),
+                )
+                .await
+                {
+                    Either::Left((Ok(()), _)) => peer
+                        .send_queue
+                        .start_send(Message::Message(message))
+                        .map_err(|_| ZmqError::NoMessage),
+                    Either::Left((Err(_), _)) => Err(ZmqError::NoMessage),
+                    // Same as SocketSend::send: the timed-out send future never took ownership
+                    // of `message`, so hand it back instead of losing it.
+                    Either::Right(_) => Err(ZmqError::ReturnToSender {
+                        reason: "ZMQ_SNDTIMEO exceeded before the reply could be queued",
+                        message,
+                    }),
+                },
+                None => peer
+                    .send_queue
+                    .send(Message::Message(message))
+                    .await
+                    .map_err(|_| ZmqError::NoMessage),
+            }
+        } else {
+            Err(ZmqError::ReturnToSender {
+                reason: "Client disconnected",
+                message,
+            })
+        }
+    }
+
+    /// Fold one chunk of a [`RepSocket::send_stream`]-style transfer into the reassembly buffer
+    /// for `(peer_id, stream_id)`. Enforces `ZMQ_MAXMSGSIZE` on the buffer as it grows, since the
+    /// chunked path must not be a way to bypass the same check the non-chunked path applies, and
+    /// caps how many incomplete streams a single peer may have open at once so a peer that never
+    /// finishes a stream can't grow `stream_reassembly` without bound.
+    fn accumulate_chunk(
+        &mut self,
+        peer_id: &PeerIdentity,
+        stream_id: u64,
+        more: bool,
+        payload: Bytes,
+    ) -> ChunkOutcome {
+        const MAX_INCOMPLETE_STREAMS_PER_PEER: usize = 16;
+
+        let key = (peer_id.clone(), stream_id);
+        if !self.stream_reassembly.contains_key(&key) {
+            let in_flight = self
+                .stream_reassembly
+                .keys()
+                .filter(|(p, _)| p == peer_id)
+                .count();
+            if in_flight >= MAX_INCOMPLETE_STREAMS_PER_PEER {
+                // Make room by discarding some other incomplete stream of this peer's rather
+                // than growing without bound -- they're all equally stuck, so which one doesn't
+                // matter.
+                if let Some(stale) = self
+                    .stream_reassembly
+                    .keys()
+                    .find(|(p, _)| p == peer_id)
+                    .cloned()
+                {
+                    self.stream_reassembly.remove(&stale);
+                }
+            }
         }
+
+        let buffer = self
+            .stream_reassembly
+            .entry(key.clone())
+            .or_insert_with(BytesMut::new);
+        buffer.extend_from_slice(&payload);
+
+        if let Some(max_size) = self.backend.socket_options.max_message_size() {
+            if buffer.len() > max_size {
+                self.stream_reassembly.remove(&key);
+                return ChunkOutcome::Oversized;
+            }
+        }
+
+        if more {
+            return ChunkOutcome::Continue;
+        }
+        let complete = self.stream_reassembly.remove(&key).unwrap_or_default();
+        ChunkOutcome::Completed(unflatten_message(complete.freeze()))
+    }
+}
+
+/// Split a raw incoming multipart message into its routing envelope (including the empty
+/// delimiter frame) and the request body, as expected by both the blocking and non-blocking
+/// recv paths. Returns `None` for a message with only one frame (e.g. a bare DEALER peer that
+/// never prepended a delimiter) instead of panicking -- a malformed message from one client
+/// must never take down the whole socket.
+fn split_envelope(mut m: ZmqMessage) -> Option<(ZmqMessage, ZmqMessage)> {
+    if m.len() <= 1 {
+        return None;
+    }
+    let mut at = 1;
+    for (index, frame) in m.iter().enumerate() {
+        if frame.is_empty() {
+            // Include delimiter in envelope.
+            at = index + 1;
+            break;
+        }
+    }
+    let data = m.split_off(at);
+    Some((m, data))
+}
+
+/// Serialize every frame of a multipart message into one length-prefixed byte stream, so a
+/// [`RepSocket::send_stream`] transfer can be split into fixed-size chunks and later
+/// reassembled by [`unflatten_message`] without losing the original frame boundaries.
+fn flatten_message(message: &ZmqMessage) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for frame in message.iter() {
+        buf.extend_from_slice(&(frame.len() as u32).to_be_bytes());
+        buf.extend_from_slice(frame);
+    }
+    buf
+}
+
+/// Inverse of [`flatten_message`]: split a fully reassembled stream back into the original
+/// frames. A truncated trailing length prefix (which should never happen once every chunk of a
+/// stream has arrived) is dropped rather than panicking.
+fn unflatten_message(mut buf: Bytes) -> ZmqMessage {
+    let mut frames = Vec::new();
+    while buf.len() >= 4 {
+        let len = u32::from_be_bytes(buf.split_to(4)[..].try_into().unwrap()) as usize;
+        if buf.len() < len {
+            break;
+        }
+        frames.push(buf.split_to(len));
+    }
+    ZmqMessage::from(frames)
+}
+
+/// Encode one chunk of a [`RepSocket::send_stream`] transfer as a 3-frame body: the marker that
+/// identifies it as a chunk, an 8-byte big-endian stream id plus a 1-byte continuation flag, and
+/// the chunk payload itself.
+fn encode_chunk(stream_id: u64, more: bool, payload: Bytes) -> ZmqMessage {
+    let mut header = Vec::with_capacity(9);
+    header.extend_from_slice(&stream_id.to_be_bytes());
+    header.push(more as u8);
+    ZmqMessage::from(vec![
+        Bytes::from_static(STREAM_CHUNK_MARKER),
+        Bytes::from(header),
+        payload,
+    ])
+}
+
+/// Recognize and decode a chunk produced by [`encode_chunk`]. Returns `None` for an ordinary,
+/// non-streamed body, including a malformed 3-frame message that merely collides with the
+/// marker frame but carries a too-short header — this must never panic, since the frames come
+/// straight off the wire from a peer we don't otherwise trust.
+fn decode_chunk(data: &ZmqMessage) -> Option<(u64, bool, Bytes)> {
+    if data.len() != 3 {
+        return None;
+    }
+    let mut frames = data.iter();
+    if frames.next()? != STREAM_CHUNK_MARKER {
+        return None;
+    }
+    let header = frames.next()?;
+    let payload = frames.next()?;
+    if header.len() < 9 {
+        return None;
+    }
+    let stream_id = u64::from_be_bytes(header[0..8].try_into().ok()?);
+    let more = header[8] != 0;
+    Some((stream_id, more, payload.clone()))
+}
+
+/// Build the ZMTP PING body: a big-endian TTL (in centiseconds, per the spec) followed by an
+/// opaque context blob that the peer must echo back unmodified in its PONG.
+fn encode_ping(ttl: Duration, context: &[u8]) -> ZmqCommand {
+    let ttl_centiseconds = (ttl.as_millis() / 10).min(u16::MAX as u128) as u16;
+    let mut data = Vec::with_capacity(2 + context.len());
+    data.extend_from_slice(&ttl_centiseconds.to_be_bytes());
+    data.extend_from_slice(context);
+    ZmqCommand {
+        name: PING_COMMAND_NAME.to_string(),
+        data: data.into(),
+    }
+}
+
+fn ping_context(command: &ZmqCommand) -> Bytes {
+    command.data.slice(2.min(command.data.len())..)
+}
+
+fn encode_pong(context: Bytes) -> ZmqCommand {
+    ZmqCommand {
+        name: PONG_COMMAND_NAME.to_string(),
+        data: context,
+    }
+}
+
+impl RepSocketBackend {
+    /// Record that a frame (data or command) was just received from `peer_id`, resetting its
+    /// heartbeat idle clock.
+    fn touch(&self, peer_id: &PeerIdentity) {
+        self.last_activity.insert(peer_id.clone(), Instant::now());
+    }
+
+    /// Record that a frame was just written out to `peer_id`, resetting the idle clock
+    /// [`RepSocketBackend::heartbeat_loop`] uses to decide whether a PING is due.
+    fn touch_sent(&self, peer_id: &PeerIdentity) {
+        self.last_sent.insert(peer_id.clone(), Instant::now());
+    }
+
+    /// Handle a decoded ZMTP command frame instead of treating it as a protocol violation.
+    /// A PING is answered with a PONG echoing the same context; every other command (including
+    /// PONG replies to our own heartbeat) only needed the liveness touch already recorded by the
+    /// caller.
+    fn handle_command(&self, peer_id: &PeerIdentity, command: ZmqCommand) {
+        if command.name == PING_COMMAND_NAME {
+            let context = ping_context(&command);
+            if let Some(mut peer) = self.peers.get_mut(peer_id) {
+                let _ = peer
+                    .send_queue
+                    .try_send(Message::Command(encode_pong(context)));
+            }
+        }
+    }
+
+    /// Wake up every `heartbeat_ivl` and, each time: evict the peer if no inbound traffic
+    /// (request, command, or PONG) has been seen in the last `heartbeat_timeout`, otherwise send
+    /// a PING if no outbound traffic has been sent in the last `heartbeat_ivl`. A single `Delay`
+    /// per iteration keeps the real cadence close to `heartbeat_ivl` instead of stacking a second
+    /// fixed sleep on top for every cycle. Exits as soon as the peer disconnects or stops
+    /// responding.
+    async fn heartbeat_loop(self: Arc<Self>, peer_id: PeerIdentity, ivl: Duration) {
+        let timeout = self.socket_options.heartbeat_timeout().unwrap_or(ivl);
+        let ttl = self.socket_options.heartbeat_ttl().unwrap_or(timeout);
+        loop {
+            Delay::new(ivl).await;
+
+            if !self.peers.contains_key(&peer_id) {
+                return;
+            }
+
+            let idle_since_recv = self
+                .last_activity
+                .get(&peer_id)
+                .map(|last| last.elapsed())
+                .unwrap_or_default();
+            if idle_since_recv >= timeout {
+                self.drop_peer(&peer_id);
+                return;
+            }
+
+            let idle_since_send = self
+                .last_sent
+                .get(&peer_id)
+                .map(|last| last.elapsed())
+                .unwrap_or_default();
+            if idle_since_send < ivl {
+                continue;
+            }
+
+            let context = self
+                .heartbeat_seq
+                .fetch_add(1, Ordering::Relaxed)
+                .to_be_bytes();
+            match self.peers.get_mut(&peer_id) {
+                Some(mut peer) => {
+                    if let Err(err) = peer
+                        .send_queue
+                        .try_send(Message::Command(encode_ping(ttl, &context)))
+                    {
+                        // Full just means the channel is backed up under ordinary
+                        // send_hwm backpressure, not that the peer is gone -- leave
+                        // last_sent alone and simply try again next ivl. Only a closed
+                        // channel means the peer actually disconnected.
+                        if !err.is_full() {
+                            return;
+                        }
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Queue a large reply to be sent to `peer_id` as a sequence of chunks.
+    fn enqueue_stream(
+        &self,
+        peer_id: &PeerIdentity,
+        envelope: Option<ZmqMessage>,
+        message: ZmqMessage,
+    ) -> ZmqResult<()> {
+        let peer = match self.peers.get(peer_id) {
+            Some(peer) => peer,
+            None => {
+                return Err(ZmqError::ReturnToSender {
+                    reason: "Client disconnected",
+                    message,
+                })
+            }
+        };
+        let payload = flatten_message(&message);
+        let stream_id = self.stream_seq.fetch_add(1, Ordering::Relaxed);
+        let mut chunks: VecDeque<Bytes> = payload
+            .chunks(STREAM_CHUNK_SIZE)
+            .map(Bytes::copy_from_slice)
+            .collect();
+        if chunks.is_empty() {
+            // An empty message still needs one terminal (more = false) chunk on the wire, or
+            // the peer's recv() would hang forever waiting for a reply that never arrives.
+            chunks.push_back(Bytes::new());
+        }
+        let job = StreamJob {
+            stream_id,
+            envelope,
+            chunks,
+        };
+        peer.stream_queue
+            .unbounded_send(job)
+            .map_err(|_| ZmqError::NoMessage)
+    }
+
+    /// Evict a peer whose stream ended in an error, that sent a malformed message, or that
+    /// missed its heartbeat timeout: report it as a disconnect and stop polling it.
+    fn drop_peer(&self, peer_id: &PeerIdentity) {
+        self.peer_disconnected(peer_id);
+        self.fair_queue_inner.lock().remove(peer_id);
+        self.last_activity.remove(peer_id);
+    }
+
+    /// Answer a message that turned out to be too large by sending `reply` (prefixed with the
+    /// sender's `envelope`) back to the peer that sent it, rather than surfacing the message
+    /// through [`SocketRecv::recv`]. The oversized message itself is discarded.
+    async fn reject_oversized(
+        &self,
+        peer_id: &PeerIdentity,
+        envelope: Option<ZmqMessage>,
+        mut reply: ZmqMessage,
+    ) {
+        if let Some(mut peer) = self.peers.get_mut(peer_id) {
+            if let Some(envelope) = envelope {
+                reply.prepend(&envelope);
+            }
+            let _ = peer.send_queue.send(Message::Message(reply)).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Socket for RepSocket {
+    fn new() -> Self {
+        Self::with_options(SocketOptions::default())
     }
 
     fn backend(&self) -> Arc<dyn MultiPeerBackend> {
@@ -72,18 +588,80 @@ impl Socket for RepSocket {
 
 impl MultiPeerBackend for RepSocketBackend {
     fn peer_connected(self: Arc<Self>, peer_id: &PeerIdentity, io: FramedIo) {
-        let (recv_queue, send_queue) = io.into_parts();
+        let (recv_queue, mut raw_send_queue) = io.into_parts();
+
+        let hwm = self.socket_options.send_hwm();
+        let (sender, mut receiver) = mpsc::channel(hwm);
+        let (stream_sender, mut stream_receiver) = mpsc::unbounded::<StreamJob>();
+        let writer_backend = self.clone();
+        let writer_peer_id = peer_id.clone();
+        async_std::task::spawn(async move {
+            let mut active: VecDeque<StreamJob> = VecDeque::new();
+            loop {
+                while let Some(Some(job)) = stream_receiver.next().now_or_never() {
+                    active.push_back(job);
+                }
+                if let Some(Some(message)) = receiver.next().now_or_never() {
+                    if raw_send_queue.send(message).await.is_err() {
+                        break;
+                    }
+                    writer_backend.touch_sent(&writer_peer_id);
+                }
+                if let Some(mut job) = active.pop_front() {
+                    if let Some(chunk) = job.chunks.pop_front() {
+                        let more = !job.chunks.is_empty();
+                        let mut chunk_message = encode_chunk(job.stream_id, more, chunk);
+                        if let Some(envelope) = &job.envelope {
+                            chunk_message.prepend(envelope);
+                        }
+                        if raw_send_queue
+                            .send(Message::Message(chunk_message))
+                            .await
+                            .is_err()
+                        {
+                            break;
+                        }
+                        writer_backend.touch_sent(&writer_peer_id);
+                        if !job.chunks.is_empty() {
+                            active.push_back(job);
+                        }
+                    }
+                    continue;
+                }
+                // Nothing ready right now: block on whichever source produces work first.
+                match select(receiver.next(), stream_receiver.next()).await {
+                    Either::Left((Some(message), _)) => {
+                        if raw_send_queue.send(message).await.is_err() {
+                            break;
+                        }
+                        writer_backend.touch_sent(&writer_peer_id);
+                    }
+                    Either::Left((None, _)) => break,
+                    Either::Right((Some(job), _)) => active.push_back(job),
+                    Either::Right((None, _)) => {}
+                }
+            }
+        });
 
         self.peers.insert(
             peer_id.clone(),
             RepPeer {
                 _identity: peer_id.clone(),
-                send_queue,
+                send_queue: sender,
+                stream_queue: stream_sender,
             },
         );
         self.fair_queue_inner
             .lock()
             .insert(peer_id.clone(), recv_queue);
+        self.last_activity.insert(peer_id.clone(), Instant::now());
+        self.last_sent.insert(peer_id.clone(), Instant::now());
+
+        if let Some(ivl) = self.socket_options.heartbeat_ivl() {
+            let backend = self.clone();
+            let peer_id = peer_id.clone();
+            async_std::task::spawn(async move { backend.heartbeat_loop(peer_id, ivl).await });
+        }
     }
 
     fn peer_disconnected(&self, peer_id: &PeerIdentity) {
@@ -91,6 +669,8 @@ impl MultiPeerBackend for RepSocketBackend {
             let _ = monitor.try_send(SocketEvent::Disconnected(peer_id.clone()));
         }
         self.peers.remove(peer_id);
+        self.last_activity.remove(peer_id);
+        self.last_sent.remove(peer_id);
     }
 }
 
@@ -117,8 +697,61 @@ impl SocketSend for RepSocket {
                     if let Some(envelope) = self.envelope.take() {
                         message.prepend(&envelope);
                     }
-                    peer.send_queue.send(Message::Message(message)).await?;
-                    Ok(())
+                    match self.backend.socket_options.send_timeout() {
+                        Some(timeout) => match select(
+                            poll_fn(|cx| peer.send_queue.poll_ready(cx)),
+                            Delay::new(timeout),
+                        )
+                        .await
+                        {
+                            Either::Left((Ok(()), _)) => peer
+                                .send_queue
+                                .start_send(Message::Message(message))
+                                .map_err(|_| ZmqError::NoMessage),
+                            Either::Left((Err(_), _)) => Err(ZmqError::NoMessage),
+                            // The timed-out send future never took ownership of `message`, so
+                            // unlike a plain `Err(ZmqError::Timeout)` the caller gets it back.
+                            Either::Right(_) => Err(ZmqError::ReturnToSender {
+                                reason: "ZMQ_SNDTIMEO exceeded before the reply could be queued",
+                                message,
+                            }),
+                        },
+                        None => peer
+                            .send_queue
+                            .send(Message::Message(message))
+                            .await
+                            .map_err(|_| ZmqError::NoMessage),
+                    }
+                } else {
+                    Err(ZmqError::ReturnToSender {
+                        reason: "Client disconnected",
+                        message,
+                    })
+                }
+            }
+            None => Err(ZmqError::ReturnToSender {
+                reason: "Unable to send reply. No request in progress",
+                message,
+            }),
+        }
+    }
+
+    fn try_send(&mut self, mut message: ZmqMessage) -> ZmqResult<()> {
+        match self.current_request.take() {
+            Some(peer_id) => {
+                if let Some(mut peer) = self.backend.peers.get_mut(&peer_id) {
+                    if let Some(envelope) = self.envelope.take() {
+                        message.prepend(&envelope);
+                    }
+                    peer.send_queue
+                        .try_send(Message::Message(message))
+                        .map_err(|err| {
+                            if err.is_full() {
+                                ZmqError::WouldBlock
+                            } else {
+                                ZmqError::NoMessage
+                            }
+                        })
                 } else {
                     Err(ZmqError::ReturnToSender {
                         reason: "Client disconnected",
@@ -138,28 +771,253 @@ impl SocketSend for RepSocket {
 impl SocketRecv for RepSocket {
     async fn recv(&mut self) -> ZmqResult<ZmqMessage> {
         loop {
-            match self.fair_queue.next().await {
-                Some((peer_id, Ok(message))) => match message {
-                    Message::Message(mut m) => {
-                        assert!(m.len() > 1);
-                        let mut at = 1;
-                        for (index, frame) in m.iter().enumerate() {
-                            if frame.is_empty() {
-                                // Include delimiter in envelope.
-                                at = index + 1;
-                                break;
+            let next = match self.backend.socket_options.recv_timeout() {
+                Some(timeout) => match select(self.fair_queue.next(), Delay::new(timeout)).await {
+                    Either::Left((next, _)) => next,
+                    Either::Right(_) => return Err(ZmqError::Timeout),
+                },
+                None => self.fair_queue.next().await,
+            };
+            match next {
+                Some((peer_id, Ok(message))) => {
+                    self.backend.touch(&peer_id);
+                    match message {
+                        Message::Message(m) => {
+                            let (envelope, data) = match split_envelope(m) {
+                                Some(parts) => parts,
+                                None => {
+                                    self.backend.drop_peer(&peer_id);
+                                    self.stream_reassembly.retain(|(p, _), _| p != &peer_id);
+                                    continue;
+                                }
+                            };
+                            match decode_chunk(&data) {
+                                Some((stream_id, more, payload)) => {
+                                    match self.accumulate_chunk(&peer_id, stream_id, more, payload)
+                                    {
+                                        ChunkOutcome::Continue => continue,
+                                        ChunkOutcome::Completed(message) => {
+                                            self.envelope = Some(envelope);
+                                            self.current_request = Some(peer_id);
+                                            return Ok(message);
+                                        }
+                                        ChunkOutcome::Oversized => {
+                                            let reply =
+                                                ZmqMessage::from("Message exceeds ZMQ_MAXMSGSIZE");
+                                            self.backend
+                                                .reject_oversized(&peer_id, Some(envelope), reply)
+                                                .await;
+                                            continue;
+                                        }
+                                    }
+                                }
+                                None => {
+                                    if let Some(max_size) =
+                                        self.backend.socket_options.max_message_size()
+                                    {
+                                        let size: usize =
+                                            data.iter().map(|frame| frame.len()).sum();
+                                        if size > max_size {
+                                            let reply =
+                                                ZmqMessage::from("Message exceeds ZMQ_MAXMSGSIZE");
+                                            self.backend
+                                                .reject_oversized(&peer_id, Some(envelope), reply)
+                                                .await;
+                                            continue;
+                                        }
+                                    }
+                                    self.envelope = Some(envelope);
+                                    self.current_request = Some(peer_id);
+                                    return Ok(data);
+                                }
                             }
                         }
-                        let data = m.split_off(at);
-                        self.envelope = Some(m);
-                        self.current_request = Some(peer_id);
-                        return Ok(data);
+                        Message::Command(command) => {
+                            self.backend.handle_command(&peer_id, command);
+                            continue;
+                        }
                     }
-                    _ => todo!(),
-                },
-                Some((_peer_id, _)) => todo!(),
+                }
+                Some((peer_id, Err(_))) => {
+                    self.backend.drop_peer(&peer_id);
+                    self.stream_reassembly.retain(|(p, _), _| p != &peer_id);
+                    continue;
+                }
                 None => return Err(ZmqError::NoMessage),
             };
         }
     }
+
+    fn try_recv(&mut self) -> ZmqResult<ZmqMessage> {
+        loop {
+            match self.fair_queue.next().now_or_never() {
+                Some(Some((peer_id, Ok(message)))) => {
+                    self.backend.touch(&peer_id);
+                    match message {
+                        Message::Message(m) => {
+                            let (envelope, data) = match split_envelope(m) {
+                                Some(parts) => parts,
+                                None => {
+                                    self.backend.drop_peer(&peer_id);
+                                    self.stream_reassembly.retain(|(p, _), _| p != &peer_id);
+                                    continue;
+                                }
+                            };
+                            match decode_chunk(&data) {
+                                Some((stream_id, more, payload)) => {
+                                    match self.accumulate_chunk(&peer_id, stream_id, more, payload)
+                                    {
+                                        ChunkOutcome::Continue => continue,
+                                        ChunkOutcome::Completed(message) => {
+                                            self.envelope = Some(envelope);
+                                            self.current_request = Some(peer_id);
+                                            return Ok(message);
+                                        }
+                                        ChunkOutcome::Oversized => {
+                                            if let Some(mut peer) =
+                                                self.backend.peers.get_mut(&peer_id)
+                                            {
+                                                let mut reply = ZmqMessage::from(
+                                                    "Message exceeds ZMQ_MAXMSGSIZE",
+                                                );
+                                                reply.prepend(&envelope);
+                                                let _ = peer
+                                                    .send_queue
+                                                    .try_send(Message::Message(reply));
+                                            }
+                                            continue;
+                                        }
+                                    }
+                                }
+                                None => {
+                                    if let Some(max_size) =
+                                        self.backend.socket_options.max_message_size()
+                                    {
+                                        let size: usize =
+                                            data.iter().map(|frame| frame.len()).sum();
+                                        if size > max_size {
+                                            if let Some(mut peer) =
+                                                self.backend.peers.get_mut(&peer_id)
+                                            {
+                                                let mut reply = ZmqMessage::from(
+                                                    "Message exceeds ZMQ_MAXMSGSIZE",
+                                                );
+                                                reply.prepend(&envelope);
+                                                let _ = peer
+                                                    .send_queue
+                                                    .try_send(Message::Message(reply));
+                                            }
+                                            continue;
+                                        }
+                                    }
+                                    self.envelope = Some(envelope);
+                                    self.current_request = Some(peer_id);
+                                    return Ok(data);
+                                }
+                            }
+                        }
+                        Message::Command(command) => {
+                            self.backend.handle_command(&peer_id, command);
+                            continue;
+                        }
+                    }
+                }
+                Some(Some((peer_id, Err(_)))) => {
+                    self.backend.drop_peer(&peer_id);
+                    self.stream_reassembly.retain(|(p, _), _| p != &peer_id);
+                    continue;
+                }
+                Some(None) => return Err(ZmqError::NoMessage),
+                None => return Err(ZmqError::WouldBlock),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_chunk_rejects_short_header_instead_of_panicking() {
+        let malformed = ZmqMessage::from(vec![
+            Bytes::from_static(STREAM_CHUNK_MARKER),
+            Bytes::from_static(b"short"),
+            Bytes::from_static(b"payload"),
+        ]);
+        assert!(decode_chunk(&malformed).is_none());
+    }
+
+    #[test]
+    fn decode_chunk_ignores_ordinary_message_with_extra_frames() {
+        let looks_like_a_chunk_but_isnt = ZmqMessage::from(vec![
+            Bytes::from_static(STREAM_CHUNK_MARKER),
+            Bytes::from_static(b"123456789"),
+            Bytes::from_static(b"payload"),
+            Bytes::from_static(b"trailing frame"),
+        ]);
+        assert!(decode_chunk(&looks_like_a_chunk_but_isnt).is_none());
+    }
+
+    #[test]
+    fn flatten_unflatten_preserves_multipart_frame_boundaries() {
+        let original = ZmqMessage::from(vec![
+            Bytes::from_static(b"frame one"),
+            Bytes::from_static(b""),
+            Bytes::from_static(b"frame three"),
+        ]);
+        let flattened = flatten_message(&original);
+        let rebuilt = unflatten_message(Bytes::from(flattened));
+        assert_eq!(rebuilt.len(), original.len());
+        for (a, b) in rebuilt.iter().zip(original.iter()) {
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn ping_pong_roundtrip_preserves_context() {
+        let ping = encode_ping(Duration::from_secs(30), b"ctx");
+        assert_eq!(ping.name, PING_COMMAND_NAME);
+        let context = ping_context(&ping);
+        assert_eq!(context, Bytes::from_static(b"ctx"));
+        let pong = encode_pong(context.clone());
+        assert_eq!(pong.name, PONG_COMMAND_NAME);
+        assert_eq!(pong.data, context);
+    }
+
+    #[test]
+    fn split_envelope_rejects_single_frame_message_instead_of_panicking() {
+        let bare = ZmqMessage::from("no delimiter");
+        assert!(split_envelope(bare).is_none());
+    }
+
+    #[test]
+    fn split_envelope_splits_at_the_empty_delimiter_frame() {
+        let message = ZmqMessage::from(vec![
+            Bytes::from_static(b"identity"),
+            Bytes::from_static(b""),
+            Bytes::from_static(b"body"),
+        ]);
+        let (envelope, data) = split_envelope(message).expect("two-frame envelope + body");
+        assert_eq!(envelope.len(), 2);
+        assert_eq!(data.len(), 1);
+    }
+
+    #[test]
+    fn encode_decode_chunk_roundtrip_empty_payload() {
+        let message = encode_chunk(3, false, Bytes::new());
+        let (stream_id, more, payload) = decode_chunk(&message).expect("valid chunk frame");
+        assert_eq!(stream_id, 3);
+        assert!(!more);
+        assert!(payload.is_empty());
+    }
+
+    #[test]
+    fn encode_decode_chunk_roundtrip() {
+        let message = encode_chunk(7, true, Bytes::from_static(b"hello"));
+        let (stream_id, more, payload) = decode_chunk(&message).expect("valid chunk frame");
+        assert_eq!(stream_id, 7);
+        assert!(more);
+        assert_eq!(payload, Bytes::from_static(b"hello"));
+    }
 }